@@ -1,34 +1,8 @@
-use image::{GenericImage, Rgb, RgbImage};
+use image::{GenericImage, ImageBuffer, Rgb, Rgba, RgbImage};
 
-struct PixelAverage {
-    avg_rb: u32,
-    avg_g: u32,
-}
-
-impl PixelAverage {
-    pub fn new() -> PixelAverage {
-        PixelAverage {
-            avg_rb: 0,
-            avg_g: 0,
-        }
-    }
-
-    pub fn add(&mut self, rgb: u32) {
-        let rb = rgb & 0x00FF00FF;
-        let g = rgb & 0x0000FF00;
-        self.avg_rb += rb;
-        self.avg_g += g;
-    }
-
-    pub fn rgb(self) -> Rgb<u8> {
-        let rb = self.avg_rb / 16;
-        let g = (self.avg_g / 16) >> 8;
-        let b = rb;
-        let r = rb >> 16;
-
-        Rgb([r as _, g as _, b as _])
-    }
-}
+/// `image` doesn't export these aliases, unlike `RgbImage`.
+pub type Rgb16Image = ImageBuffer<Rgb<u16>, Vec<u16>>;
+pub type Rgba16Image = ImageBuffer<Rgba<u16>, Vec<u16>>;
 
 pub trait ToRgb {
     fn rgb(&self) -> Rgb<u8>;
@@ -50,6 +24,78 @@ impl ToRgb for RgbPixel {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Bt601,
+    Bt709,
+    Bt2020,
+}
+
+/// Studio (limited, 16-235/16-240) vs full (0-255) swing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    Limited,
+    Full,
+}
+
+/// Fixed-point 8.8 YUV→RGB coefficients for a given colorimetry.
+#[derive(Debug, Clone, Copy)]
+pub struct YuvMatrix {
+    y_mul: i32,
+    v_to_r: i32,
+    u_to_g: i32,
+    v_to_g: i32,
+    u_to_b: i32,
+    y_offset: i32,
+}
+
+impl YuvMatrix {
+    pub fn new(space: ColorSpace, range: Range) -> YuvMatrix {
+        let (kr, kb) = match space {
+            ColorSpace::Bt601 => (0.299_f64, 0.114_f64),
+            ColorSpace::Bt709 => (0.2126_f64, 0.0722_f64),
+            ColorSpace::Bt2020 => (0.2627_f64, 0.0593_f64),
+        };
+        let kg = 1.0 - kr - kb;
+
+        let (luma_scale, chroma_scale, y_offset) = match range {
+            Range::Limited => (255.0 / 219.0, 255.0 / 224.0, 16),
+            Range::Full => (1.0, 1.0, 0),
+        };
+
+        let fixed = |v: f64| (v * 256.0).round() as i32;
+
+        YuvMatrix {
+            y_mul: fixed(luma_scale),
+            v_to_r: fixed(2.0 * (1.0 - kr) * chroma_scale),
+            u_to_g: fixed(2.0 * (1.0 - kb) * kb / kg * chroma_scale),
+            v_to_g: fixed(2.0 * (1.0 - kr) * kr / kg * chroma_scale),
+            u_to_b: fixed(2.0 * (1.0 - kb) * chroma_scale),
+            y_offset,
+        }
+    }
+
+    /// For uploading to the [`gpu`] uniform buffer.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn coefficients(&self) -> [i32; 6] {
+        [
+            self.y_mul,
+            self.v_to_r,
+            self.u_to_g,
+            self.v_to_g,
+            self.u_to_b,
+            self.y_offset,
+        ]
+    }
+}
+
+impl Default for YuvMatrix {
+    /// The matrix this crate hardcoded before colorimetry became configurable.
+    fn default() -> YuvMatrix {
+        YuvMatrix::new(ColorSpace::Bt601, Range::Limited)
+    }
+}
+
 pub struct YUV420Pixel {
     dat: [u8; 3],
 }
@@ -58,20 +104,18 @@ impl YUV420Pixel {
     pub fn new(c: u8, d: u8, e: u8) -> YUV420Pixel {
         YUV420Pixel { dat: [c, d, e] }
     }
-}
 
-impl ToRgb for YUV420Pixel {
-    fn rgb(&self) -> Rgb<u8> {
+    pub fn rgb_with(&self, matrix: &YuvMatrix) -> Rgb<u8> {
         let y = self.dat[0] as i32;
         let u = self.dat[1] as i32;
         let v = self.dat[2] as i32;
-        let c = y - 16;
+        let c = y - matrix.y_offset;
         let d = u - 128;
         let e = v - 128;
 
-        let r = (298 * c + 409 * e + 128) >> 8;
-        let g = (298 * c - 100 * d - 208 * e + 128) >> 8;
-        let b = (298 * c + 516 * d + 128) >> 8;
+        let r = (matrix.y_mul * c + matrix.v_to_r * e + 128) >> 8;
+        let g = (matrix.y_mul * c - matrix.u_to_g * d - matrix.v_to_g * e + 128) >> 8;
+        let b = (matrix.y_mul * c + matrix.u_to_b * d + 128) >> 8;
 
         let clamp = |v| {
             if v > 0xFF {
@@ -89,6 +133,95 @@ impl ToRgb for YUV420Pixel {
     }
 }
 
+impl ToRgb for YUV420Pixel {
+    fn rgb(&self) -> Rgb<u8> {
+        self.rgb_with(&YuvMatrix::default())
+    }
+}
+
+#[cfg(test)]
+mod yuv_matrix_tests {
+    use super::*;
+
+    /// Must match the old hardcoded 298/409/100/208/516 coefficients.
+    #[test]
+    fn default_matches_original_bt601_limited_coefficients() {
+        let matrix = YuvMatrix::default();
+
+        for (y, u, v) in [(200u8, 90u8, 160u8), (16, 128, 128), (235, 16, 240), (0, 255, 0)] {
+            let c = y as i32 - 16;
+            let d = u as i32 - 128;
+            let e = v as i32 - 128;
+
+            let r = (298 * c + 409 * e + 128) >> 8;
+            let g = (298 * c - 100 * d - 208 * e + 128) >> 8;
+            let b = (298 * c + 516 * d + 128) >> 8;
+            let clamp = |v: i32| v.clamp(0, 0xFF) as u8;
+            let expected = Rgb([clamp(r), clamp(g), clamp(b)]);
+
+            let actual = YUV420Pixel::new(y, u, v).rgb_with(&matrix);
+            assert_eq!(actual, expected, "for (y, u, v) = ({y}, {u}, {v})");
+        }
+    }
+
+    /// Hand-derived 8.8 fixed-point coefficients for a `(space, range)` pair,
+    /// independent of `YuvMatrix::new`'s own arithmetic.
+    struct ExpectedCoefficients {
+        y_mul: i32,
+        v_to_r: i32,
+        u_to_g: i32,
+        v_to_g: i32,
+        u_to_b: i32,
+        y_offset: i32,
+    }
+
+    fn check_matrix(space: ColorSpace, range: Range, expected: ExpectedCoefficients) {
+        let matrix = YuvMatrix::new(space, range);
+
+        for (y, u, v) in [(200u8, 90u8, 160u8), (16, 128, 128), (235, 16, 240), (0, 255, 0)] {
+            let c = y as i32 - expected.y_offset;
+            let d = u as i32 - 128;
+            let e = v as i32 - 128;
+
+            let r = (expected.y_mul * c + expected.v_to_r * e + 128) >> 8;
+            let g = (expected.y_mul * c - expected.u_to_g * d - expected.v_to_g * e + 128) >> 8;
+            let b = (expected.y_mul * c + expected.u_to_b * d + 128) >> 8;
+            let clamp = |v: i32| v.clamp(0, 0xFF) as u8;
+            let expected_rgb = Rgb([clamp(r), clamp(g), clamp(b)]);
+
+            let actual = YUV420Pixel::new(y, u, v).rgb_with(&matrix);
+            assert_eq!(actual, expected_rgb, "for (y, u, v) = ({y}, {u}, {v})");
+        }
+    }
+
+    #[test]
+    fn bt709_limited_matches_hand_derived_coefficients() {
+        check_matrix(
+            ColorSpace::Bt709,
+            Range::Limited,
+            ExpectedCoefficients { y_mul: 298, v_to_r: 459, u_to_g: 55, v_to_g: 136, u_to_b: 541, y_offset: 16 },
+        );
+    }
+
+    #[test]
+    fn bt2020_limited_matches_hand_derived_coefficients() {
+        check_matrix(
+            ColorSpace::Bt2020,
+            Range::Limited,
+            ExpectedCoefficients { y_mul: 298, v_to_r: 430, u_to_g: 48, v_to_g: 167, u_to_b: 548, y_offset: 16 },
+        );
+    }
+
+    #[test]
+    fn bt601_full_range_matches_hand_derived_coefficients() {
+        check_matrix(
+            ColorSpace::Bt601,
+            Range::Full,
+            ExpectedCoefficients { y_mul: 256, v_to_r: 359, u_to_g: 88, v_to_g: 183, u_to_b: 454, y_offset: 0 },
+        );
+    }
+}
+
 pub struct Rgb565 {
     dat: u16,
 }
@@ -110,6 +243,344 @@ impl ToRgb for Rgb565 {
     }
 }
 
+/// A packed `XRGB2101010`/`ARGB2101010` pixel (10 bits per channel in a `u32`).
+pub struct Rgb2101010 {
+    dat: u32,
+}
+
+impl Rgb2101010 {
+    pub fn new(dat: u32) -> Self {
+        Rgb2101010 { dat }
+    }
+}
+
+impl ToRgb for Rgb2101010 {
+    fn rgb(&self) -> Rgb<u8> {
+        let channel = |shift: u32| {
+            let c10 = (self.dat >> shift) & 0x3FF;
+            ((c10 * 255 + 511) / 1023) as u8
+        };
+        Rgb([channel(20), channel(10), channel(0)])
+    }
+}
+
+/// Byte order of a multi-byte-per-channel pixel format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// A 16-bit-per-channel RGB pixel, read from 3 consecutive 16-bit samples.
+pub struct Rgb16 {
+    dat: [u16; 3],
+}
+
+impl Rgb16 {
+    pub fn from_bytes(bytes: &[u8], endian: Endian) -> Rgb16 {
+        let read = |i| match endian {
+            Endian::Big => u16::from_be_bytes([bytes[i], bytes[i + 1]]),
+            Endian::Little => u16::from_le_bytes([bytes[i], bytes[i + 1]]),
+        };
+        Rgb16 {
+            dat: [read(0), read(2), read(4)],
+        }
+    }
+
+    /// Unlike [`ToRgb`], keeps full 16-bit precision.
+    pub fn rgb(&self) -> Rgb<u16> {
+        Rgb(self.dat)
+    }
+}
+
+/// A 16-bit-per-channel RGBA pixel, read from 4 consecutive 16-bit samples.
+pub struct Rgba16 {
+    dat: [u16; 4],
+}
+
+impl Rgba16 {
+    pub fn from_bytes(bytes: &[u8], endian: Endian) -> Rgba16 {
+        let read = |i| match endian {
+            Endian::Big => u16::from_be_bytes([bytes[i], bytes[i + 1]]),
+            Endian::Little => u16::from_le_bytes([bytes[i], bytes[i + 1]]),
+        };
+        Rgba16 {
+            dat: [read(0), read(2), read(4), read(6)],
+        }
+    }
+
+    pub fn rgba(&self) -> Rgba<u16> {
+        Rgba(self.dat)
+    }
+}
+
+#[cfg(test)]
+mod wide_pixel_tests {
+    use super::*;
+
+    #[test]
+    fn rgb2101010_extracts_a_pure_channel_from_its_packed_word() {
+        // Bits 20-29 (the red channel at shift 20) set, everything else clear.
+        let pixel = Rgb2101010::new(0x3FF00000);
+        assert_eq!(pixel.rgb(), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn rgb16_reads_big_and_little_endian_samples() {
+        let be_bytes = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+        assert_eq!(
+            Rgb16::from_bytes(&be_bytes, Endian::Big).rgb(),
+            Rgb([0x1234, 0x5678, 0x9ABC])
+        );
+
+        let le_bytes = [0x34, 0x12, 0x78, 0x56, 0xBC, 0x9A];
+        assert_eq!(
+            Rgb16::from_bytes(&le_bytes, Endian::Little).rgb(),
+            Rgb([0x1234, 0x5678, 0x9ABC])
+        );
+    }
+
+    #[test]
+    fn rgba16_reads_big_and_little_endian_samples() {
+        let be_bytes = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
+        assert_eq!(
+            Rgba16::from_bytes(&be_bytes, Endian::Big).rgba(),
+            Rgba([0x1234, 0x5678, 0x9ABC, 0xDEF0])
+        );
+
+        let le_bytes = [0x34, 0x12, 0x78, 0x56, 0xBC, 0x9A, 0xF0, 0xDE];
+        assert_eq!(
+            Rgba16::from_bytes(&le_bytes, Endian::Little).rgba(),
+            Rgba([0x1234, 0x5678, 0x9ABC, 0xDEF0])
+        );
+    }
+
+    #[test]
+    fn decode_image_2101010_respects_pitch_wider_than_the_image() {
+        // Two 2-pixel-wide rows, each padded to a 3-word pitch: the third
+        // word of each row must be skipped, not read as image data.
+        let mapping = [0x3FF00000u32, 0x000003FFu32, 0x000FFC00u32, 0u32, 0u32, 0u32];
+        let img = decode_image_2101010(&mapping, 12, (2, 2));
+        assert_eq!(*img.get_pixel(0, 0), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(1, 0), Rgb([0, 0, 255]));
+        assert_eq!(*img.get_pixel(0, 1), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn decode_image_rgb16_respects_pitch_wider_than_the_image() {
+        // One pixel per row (6 bytes), each row padded to a 9-byte pitch.
+        let mapping = [
+            0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xFF, 0xFF, 0xFF, //
+            0x11, 0x11, 0x22, 0x22, 0x33, 0x33, 0xFF, 0xFF, 0xFF,
+        ];
+        let img = decode_image_rgb16(&mapping, 9, (1, 2), Endian::Big);
+        assert_eq!(*img.get_pixel(0, 0), Rgb([0x1234, 0x5678, 0x9ABC]));
+        assert_eq!(*img.get_pixel(0, 1), Rgb([0x1111, 0x2222, 0x3333]));
+    }
+}
+
+/// Resampling kernels for [`resize`]. `Nearest` and `Bilinear` are cheap
+/// approximations; `Lanczos3` gives the sharpest result at a higher cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    Lanczos3,
+}
+
+impl Filter {
+    fn support(&self) -> f64 {
+        match self {
+            Filter::Nearest => 0.5,
+            Filter::Bilinear => 1.0,
+            Filter::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(&self, x: f64) -> f64 {
+        match self {
+            Filter::Nearest => {
+                if x.abs() < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Bilinear => {
+                let x = x.abs();
+                if x < 1.0 {
+                    1.0 - x
+                } else {
+                    0.0
+                }
+            }
+            Filter::Lanczos3 => {
+                let a = 3.0_f64;
+                if x == 0.0 {
+                    1.0
+                } else if x.abs() < a {
+                    let px = std::f64::consts::PI * x;
+                    a * px.sin() * (px / a).sin() / (px * px)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Fixed-point (Q18.14) weights one output pixel needs from a source axis.
+struct AxisWeights {
+    start: u32,
+    weights: Vec<i32>,
+}
+
+const WEIGHT_SHIFT: u32 = 14;
+const WEIGHT_SCALE: f64 = (1u32 << WEIGHT_SHIFT) as f64;
+
+/// Precomputes, for every destination pixel along one axis, the weighted
+/// window of source pixels the filter needs. Widening the kernel support by
+/// `1/scale` when downscaling keeps it from aliasing.
+fn axis_weights(src_len: u32, dst_len: u32, filter: Filter) -> Vec<AxisWeights> {
+    let scale = dst_len as f64 / src_len as f64;
+    let filter_scale = if scale < 1.0 { 1.0 / scale } else { 1.0 };
+    let support = filter.support() * filter_scale;
+
+    (0..dst_len)
+        .map(|dst| {
+            let center = (dst as f64 + 0.5) / scale;
+            let first = (center - support).floor() as i64;
+            let last = (center + support).ceil() as i64;
+
+            let taps: Vec<(i64, f64)> = (first..=last)
+                .map(|src| (src, filter.weight((src as f64 + 0.5 - center) / filter_scale)))
+                .filter(|&(_, w)| w != 0.0)
+                .collect();
+
+            let sum: f64 = taps.iter().map(|&(_, w)| w).sum();
+            let start = taps
+                .first()
+                .map_or(0, |&(s, _)| s.clamp(0, src_len as i64 - 1)) as u32;
+            let end = taps
+                .last()
+                .map_or(0, |&(s, _)| s.clamp(0, src_len as i64 - 1)) as u32;
+
+            let mut weights = vec![0i32; (end - start + 1) as usize];
+            for &(src, w) in &taps {
+                let clamped = src.clamp(0, src_len as i64 - 1) as u32 - start;
+                weights[clamped as usize] += ((w / sum) * WEIGHT_SCALE).round() as i32;
+            }
+
+            AxisWeights { start, weights }
+        })
+        .collect()
+}
+
+fn round_channel(acc: i32) -> u8 {
+    let rounded = (acc + (1 << (WEIGHT_SHIFT - 1))) >> WEIGHT_SHIFT;
+    rounded.clamp(0, 0xFF) as u8
+}
+
+/// Separable resize of `img` to `target` using `filter`: a horizontal pass
+/// followed by a vertical pass, each a weighted sum of source pixels
+/// accumulated in `i32` before rounding back to `u8`.
+pub fn resize(img: &RgbImage, target: (u32, u32), filter: Filter) -> RgbImage {
+    let (src_w, src_h) = img.dimensions();
+    if (src_w, src_h) == target {
+        return img.clone();
+    }
+
+    let col_weights = axis_weights(src_w, target.0, filter);
+    let row_weights = axis_weights(src_h, target.1, filter);
+
+    let mut horizontal = RgbImage::new(target.0, src_h);
+    for y in 0..src_h {
+        for (x, w) in col_weights.iter().enumerate() {
+            let mut acc = [0i32; 3];
+            for (i, &weight) in w.weights.iter().enumerate() {
+                let px = img.get_pixel(w.start + i as u32, y);
+                for c in 0..3 {
+                    acc[c] += px[c] as i32 * weight;
+                }
+            }
+            let px = Rgb([
+                round_channel(acc[0]),
+                round_channel(acc[1]),
+                round_channel(acc[2]),
+            ]);
+            unsafe { horizontal.unsafe_put_pixel(x as u32, y, px) };
+        }
+    }
+
+    let mut out = RgbImage::new(target.0, target.1);
+    for (y, w) in row_weights.iter().enumerate() {
+        for x in 0..target.0 {
+            let mut acc = [0i32; 3];
+            for (i, &weight) in w.weights.iter().enumerate() {
+                let px = horizontal.get_pixel(x, w.start + i as u32);
+                for c in 0..3 {
+                    acc[c] += px[c] as i32 * weight;
+                }
+            }
+            let px = Rgb([
+                round_channel(acc[0]),
+                round_channel(acc[1]),
+                round_channel(acc[2]),
+            ]);
+            unsafe { out.unsafe_put_pixel(x, y as u32, px) };
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod resize_tests {
+    use super::*;
+
+    fn gray_row(values: &[u8]) -> RgbImage {
+        let mut img = RgbImage::new(values.len() as u32, 1);
+        for (x, &v) in values.iter().enumerate() {
+            unsafe { img.unsafe_put_pixel(x as u32, 0, Rgb([v, v, v])) };
+        }
+        img
+    }
+
+    #[test]
+    fn no_op_when_target_matches_source_dimensions() {
+        let img = gray_row(&[10, 20, 30]);
+        let resized = resize(&img, img.dimensions(), Filter::Lanczos3);
+        assert_eq!(resized, img);
+    }
+
+    #[test]
+    fn nearest_upscale_of_two_pixels_repeats_each_twice() {
+        let img = gray_row(&[0, 255]);
+        let resized = resize(&img, (4, 1), Filter::Nearest);
+        assert_eq!(
+            resized.into_raw(),
+            vec![0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn bilinear_upscale_of_two_pixels_interpolates_linearly() {
+        let img = gray_row(&[0, 255]);
+        let resized = resize(&img, (4, 1), Filter::Bilinear);
+        let gray: Vec<u8> = resized.pixels().map(|p| p[0]).collect();
+        assert_eq!(gray, vec![0, 64, 191, 255]);
+    }
+
+    #[test]
+    fn single_source_pixel_clamps_every_tap_to_it() {
+        // With a 1-wide source, even Lanczos3's wide support window has
+        // nowhere else to land: every output pixel must clamp back to it.
+        let img = gray_row(&[42]);
+        let resized = resize(&img, (5, 1), Filter::Lanczos3);
+        assert!(resized.pixels().all(|p| *p == Rgb([42, 42, 42])));
+    }
+}
+
 pub fn rgb565_to_rgb888(mapping: &[u16], pitch: u32, size: (u32, u32)) -> RgbImage {
     let mut img = RgbImage::new(size.0, size.1);
 
@@ -146,10 +617,70 @@ pub fn decode_image(mapping: &[u32], pitch: u32, size: (u32, u32)) -> RgbImage {
     img
 }
 
+pub fn decode_image_2101010(mapping: &[u32], pitch: u32, size: (u32, u32)) -> RgbImage {
+    let mut img = RgbImage::new(size.0, size.1);
+
+    let bytepitch = pitch / 4;
+
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let offset = y * bytepitch + x;
+            let v = Rgb2101010::new(mapping[offset as usize]);
+
+            unsafe { img.unsafe_put_pixel(x, y, v.rgb()) };
+        }
+    }
+
+    img
+}
+
+pub fn decode_image_rgb16(
+    mapping: &[u8],
+    pitch: u32,
+    size: (u32, u32),
+    endian: Endian,
+) -> Rgb16Image {
+    let mut img = Rgb16Image::new(size.0, size.1);
+
+    for y in 0..size.1 {
+        let row = (y * pitch) as usize;
+        for x in 0..size.0 {
+            let offset = row + (x * 6) as usize;
+            let v = Rgb16::from_bytes(&mapping[offset..offset + 6], endian);
+
+            unsafe { img.unsafe_put_pixel(x, y, v.rgb()) };
+        }
+    }
+
+    img
+}
+
+pub fn decode_image_rgba16(
+    mapping: &[u8],
+    pitch: u32,
+    size: (u32, u32),
+    endian: Endian,
+) -> Rgba16Image {
+    let mut img = Rgba16Image::new(size.0, size.1);
+
+    for y in 0..size.1 {
+        let row = (y * pitch) as usize;
+        for x in 0..size.0 {
+            let offset = row + (x * 8) as usize;
+            let v = Rgba16::from_bytes(&mapping[offset..offset + 8], endian);
+
+            unsafe { img.unsafe_put_pixel(x, y, v.rgba()) };
+        }
+    }
+
+    img
+}
+
 pub fn decode_image_multichannel(
     mappings: [&[u8]; 3],
     size: (u32, u32),
     pitches: [u32; 3],
+    matrix: &YuvMatrix,
 ) -> RgbImage {
     let mut img = RgbImage::new(size.0, size.1);
 
@@ -164,7 +695,7 @@ pub fn decode_image_multichannel(
                 mappings[2][offset2],
             );
 
-            unsafe { img.unsafe_put_pixel(x, y, yuv.rgb()) };
+            unsafe { img.unsafe_put_pixel(x, y, yuv.rgb_with(matrix)) };
         }
     }
 
@@ -175,28 +706,11 @@ pub fn decode_small_image_multichannel(
     mappings: [&[u8]; 3],
     size: (u32, u32),
     pitches: [u32; 3],
+    matrix: &YuvMatrix,
+    filter: Filter,
 ) -> RgbImage {
-    let halfsize = (size.0 / 2, size.1 / 2);
-    let mut img = RgbImage::new(halfsize.0, halfsize.1);
-
-    for y in 0..halfsize.1 {
-        for x in 0..halfsize.0 {
-            let offset: usize = (2 * y * pitches[0] + 2 * x) as _;
-            let offset1: usize = (y * pitches[1] + x) as _;
-            let offset2: usize = (y * pitches[2] + x) as _;
-            let yat = |offset| mappings[0][offset] as u32;
-            let yval = (yat(offset)
-                + yat(offset + 1)
-                + yat(offset + pitches[0] as usize)
-                + yat(offset + pitches[0] as usize + 1))
-                / 4;
-            let yuv = YUV420Pixel::new(yval as _, mappings[1][offset1], mappings[2][offset2]);
-
-            unsafe { img.unsafe_put_pixel(x, y, yuv.rgb()) };
-        }
-    }
-
-    img
+    let full = decode_image_multichannel(mappings, size, pitches, matrix);
+    resize(&full, (size.0 / 2, size.1 / 2), filter)
 }
 
 pub fn decode_tiled_small_image(
@@ -204,133 +718,596 @@ pub fn decode_tiled_small_image(
     tilesize: u32,
     tiles: (u32, u32),
     size: (u32, u32),
+    filter: Filter,
 ) -> RgbImage {
-    let mut img = RgbImage::new(tiles.0 * tilesize / 4, tiles.1 * tilesize / 4);
+    // `mapping`'s u32 words are the same packed BGRX8888 pixels `decode` reads
+    // as bytes; reinterpret rather than copy.
+    let bytes =
+        unsafe { std::slice::from_raw_parts(mapping.as_ptr() as *const u8, mapping.len() * 4) };
+    let full = to_image(bytes, tilesize, tiles, size);
+    resize(&full, (size.0 / 4, size.1 / 4), filter)
+}
 
-    let mut i = 0;
+/// Describes a tiled (or linear) framebuffer memory layout for the generic `decode`.
+/// A layout is made of "subtiles" (`subtile_size`); `subtile_order` gives the scan
+/// order of the four subtiles making up one block, for an even tile row.
+pub trait TileFormat {
+    /// Subtile width and height, in destination pixels.
+    fn subtile_size(&self) -> (u32, u32);
+    /// Bytes one destination pixel consumes in `mapping`.
+    fn bytes_per_pixel(&self) -> u32;
+    /// Scan order of the four subtiles making up one T-tile, as (x, y) subtile offsets.
+    fn subtile_order(&self) -> [(u32, u32); 4];
 
-    let mut avg_16 = |x, y| {
-        let mut avg = PixelAverage::new();
-        for n in 0..16 {
-            avg.add(mapping[i + n]);
-        }
-        unsafe {
-            img.unsafe_put_pixel(x, y, avg.rgb());
-        }
-        i = i + 16;
-    };
-
-    let mut copy_16x4_px = |x, y| {
-        avg_16(x, y);
-        avg_16(x + 1, y);
-        avg_16(x + 2, y);
-        avg_16(x + 3, y);
-    };
-
-    let mut copy_16x16_px = |x, y| {
-        copy_16x4_px(x, y);
-        copy_16x4_px(x, y + 1);
-        copy_16x4_px(x, y + 2);
-        copy_16x4_px(x, y + 3);
-    };
+    /// Whether odd tile rows reverse horizontal direction and rotate `subtile_order`.
+    fn alternates_rows(&self) -> bool {
+        true
+    }
+
+    /// Byte offset of pixel `(px, py)` within the `block_index`'th subtile, counting
+    /// subtiles in the order `decode` visits them. Defaults to row-major; override
+    /// for formats with their own intra-subtile byte order.
+    fn pixel_offset(&self, block_index: u32, px: (u32, u32)) -> usize {
+        let (sw, sh) = self.subtile_size();
+        let bpp = self.bytes_per_pixel() as usize;
+        let block_bytes = (sw * sh) as usize * bpp;
+        block_index as usize * block_bytes + (px.1 * sw + px.0) as usize * bpp
+    }
+}
+
+/// The VC4 T-tile layout: packed BGRX8888 pixels in 16x16 subtiles, four of
+/// which form a 32x32 T-tile arranged in the classic 2x2 pattern.
+pub struct Vc4Tile;
+
+impl TileFormat for Vc4Tile {
+    fn subtile_size(&self) -> (u32, u32) {
+        (16, 16)
+    }
+
+    fn bytes_per_pixel(&self) -> u32 {
+        4
+    }
+
+    fn subtile_order(&self) -> [(u32, u32); 4] {
+        [(0, 0), (0, 1), (1, 1), (1, 0)]
+    }
+
+    /// A 16x16 subtile isn't stored row-major: it's a 4x4 grid of 4x4-pixel
+    /// microtiles, themselves scanned row-major, both in row-major order.
+    fn pixel_offset(&self, block_index: u32, px: (u32, u32)) -> usize {
+        const MICROTILE: u32 = 4;
+        let bpp = self.bytes_per_pixel() as usize;
+        let block_bytes = (16 * 16) as usize * bpp;
+
+        let (mx, my) = (px.0 % MICROTILE, px.1 % MICROTILE);
+        let (gx, gy) = (px.0 / MICROTILE, px.1 / MICROTILE);
+        let microtile_index = gy * MICROTILE + gx;
+        let pixel_in_microtile = my * MICROTILE + mx;
+
+        block_index as usize * block_bytes
+            + (microtile_index * MICROTILE * MICROTILE + pixel_in_microtile) as usize * bpp
+    }
+}
+
+/// The tile row order rotated by two positions — what T-tile memory layout
+/// actually uses on odd rows, as opposed to reversing the even-row order.
+fn rotated_order(order: [(u32, u32); 4]) -> [(u32, u32); 4] {
+    [order[2], order[3], order[0], order[1]]
+}
+
+/// Walks `mapping` block by block according to `format` and writes an `RgbImage`.
+/// When `format.alternates_rows()` is set, tiles are visited back to front on
+/// odd tile rows, and within them subtiles use `subtile_order` rotated by two.
+pub fn decode<F: TileFormat>(
+    format: &F,
+    mapping: &[u8],
+    tiles: (u32, u32),
+    size: (u32, u32),
+) -> RgbImage {
+    let (sw, sh) = format.subtile_size();
+    let tile_w = sw * 2;
+    let tile_h = sh * 2;
+    let mut img = RgbImage::new(tiles.0 * tile_w, tiles.1 * tile_h);
+    let order = format.subtile_order();
+    let odd_row_order = rotated_order(order);
+    let alternates_rows = format.alternates_rows();
+    let mut block_index = 0;
 
     for ytile in 0..tiles.1 {
-        if ytile % 2 == 0 {
-            let mut copy_tile = |x, y| {
-                copy_16x16_px(x, y);
-                copy_16x16_px(x, y + 4);
-                copy_16x16_px(x + 4, y + 4);
-                copy_16x16_px(x + 4, y);
-            };
+        let reversed = alternates_rows && ytile % 2 != 0;
 
-            for xtile in 0..tiles.0 {
-                copy_tile(xtile * tilesize / 4, ytile * tilesize / 4);
-            }
+        let xtiles: Box<dyn Iterator<Item = u32>> = if reversed {
+            Box::new((0..tiles.0).rev())
         } else {
-            let mut copy_tile = |x, y| {
-                copy_16x16_px(x + 4, y + 4);
-                copy_16x16_px(x + 4, y);
-                copy_16x16_px(x, y);
-                copy_16x16_px(x, y + 4);
-            };
+            Box::new(0..tiles.0)
+        };
+
+        for xtile in xtiles {
+            let tile_x = xtile * tile_w;
+            let tile_y = ytile * tile_h;
+
+            let subtiles: &[(u32, u32); 4] = if reversed { &odd_row_order } else { &order };
+
+            for &(sx, sy) in subtiles {
+                let ox = tile_x + sx * sw;
+                let oy = tile_y + sy * sh;
 
-            for xtile in (0..tiles.0).rev() {
-                copy_tile(xtile * tilesize / 4, ytile * tilesize / 4);
+                for py in 0..sh {
+                    for px in 0..sw {
+                        let offset = format.pixel_offset(block_index, (px, py));
+                        let color = Rgb([mapping[offset + 2], mapping[offset + 1], mapping[offset]]);
+                        unsafe { img.unsafe_put_pixel(ox + px, oy + py, color) };
+                    }
+                }
+
+                block_index += 1;
             }
         }
     }
 
-    img.sub_image(0, 0, size.0 / 4, size.1 / 4).to_image()
+    img.sub_image(0, 0, size.0, size.1).to_image()
 }
 
 pub fn to_image(mapping: &[u8], tilesize: u32, tiles: (u32, u32), size: (u32, u32)) -> RgbImage {
-    let mut img = RgbImage::new(tiles.0 * tilesize, tiles.1 * tilesize);
-    let mut i = 0;
-
-    let mut copy_px = |x, y| {
-        let color = Rgb([
-            mapping[(i + 2) as usize],
-            mapping[(i + 1) as usize],
-            mapping[(i + 0) as usize],
-        ]);
-        unsafe {
-            img.unsafe_put_pixel(x, y, color);
+    debug_assert_eq!(tilesize, 32, "VC4 T-tiles are 32x32 (four 16x16 subtiles)");
+    decode(&Vc4Tile, mapping, tiles, size)
+}
+
+#[cfg(test)]
+mod tile_decode_tests {
+    use super::*;
+
+    /// The pre-`TileFormat` `to_image`, kept only to regression-test that the
+    /// generic `decode` reproduces its exact pixel layout.
+    fn baseline_to_image(
+        mapping: &[u8],
+        tilesize: u32,
+        tiles: (u32, u32),
+        size: (u32, u32),
+    ) -> RgbImage {
+        let mut img = RgbImage::new(tiles.0 * tilesize, tiles.1 * tilesize);
+        let mut i = 0;
+
+        let mut copy_px = |x, y| {
+            let color = Rgb([
+                mapping[(i + 2) as usize],
+                mapping[(i + 1) as usize],
+                mapping[i as usize],
+            ]);
+            unsafe {
+                img.unsafe_put_pixel(x, y, color);
+            }
+            i += 4;
+        };
+        let mut copy_4_px = |x, y| {
+            copy_px(x, y);
+            copy_px(x + 1, y);
+            copy_px(x + 2, y);
+            copy_px(x + 3, y);
+        };
+
+        let mut copy_4x4_px = |x, y| {
+            copy_4_px(x, y);
+            copy_4_px(x, y + 1);
+            copy_4_px(x, y + 2);
+            copy_4_px(x, y + 3);
+        };
+
+        let mut copy_16x4_px = |x, y| {
+            copy_4x4_px(x, y);
+            copy_4x4_px(x + 4, y);
+            copy_4x4_px(x + 8, y);
+            copy_4x4_px(x + 12, y);
+        };
+
+        let mut copy_16x16_px = |x, y| {
+            copy_16x4_px(x, y);
+            copy_16x4_px(x, y + 4);
+            copy_16x4_px(x, y + 8);
+            copy_16x4_px(x, y + 12);
+        };
+
+        for ytile in 0..tiles.1 {
+            if ytile % 2 == 0 {
+                let mut copy_tile = |x, y| {
+                    copy_16x16_px(x, y);
+                    copy_16x16_px(x, y + 16);
+                    copy_16x16_px(x + 16, y + 16);
+                    copy_16x16_px(x + 16, y);
+                };
+
+                for xtile in 0..tiles.0 {
+                    copy_tile(xtile * tilesize, ytile * tilesize);
+                }
+            } else {
+                let mut copy_tile = |x, y| {
+                    copy_16x16_px(x + 16, y + 16);
+                    copy_16x16_px(x + 16, y);
+                    copy_16x16_px(x, y);
+                    copy_16x16_px(x, y + 16);
+                };
+
+                for xtile in (0..tiles.0).rev() {
+                    copy_tile(xtile * tilesize, ytile * tilesize);
+                }
+            }
         }
-        i = i + 4;
-    };
-    let mut copy_4_px = |x, y| {
-        copy_px(x, y);
-        copy_px(x + 1, y);
-        copy_px(x + 2, y);
-        copy_px(x + 3, y);
-    };
-
-    let mut copy_4x4_px = |x, y| {
-        copy_4_px(x, y);
-        copy_4_px(x, y + 1);
-        copy_4_px(x, y + 2);
-        copy_4_px(x, y + 3);
-    };
-
-    let mut copy_16x4_px = |x, y| {
-        copy_4x4_px(x, y);
-        copy_4x4_px(x + 4, y);
-        copy_4x4_px(x + 8, y);
-        copy_4x4_px(x + 12, y);
-    };
-
-    let mut copy_16x16_px = |x, y| {
-        copy_16x4_px(x, y);
-        copy_16x4_px(x, y + 4);
-        copy_16x4_px(x, y + 8);
-        copy_16x4_px(x, y + 12);
-    };
 
-    for ytile in 0..tiles.1 {
-        if ytile % 2 == 0 {
-            let mut copy_tile = |x, y| {
-                copy_16x16_px(x, y);
-                copy_16x16_px(x, y + 16);
-                copy_16x16_px(x + 16, y + 16);
-                copy_16x16_px(x + 16, y);
-            };
+        img.sub_image(0, 0, size.0, size.1).to_image()
+    }
+
+    #[test]
+    fn decode_matches_pre_refactor_to_image() {
+        let tiles = (2, 2);
+        let tilesize = 32;
+        let size = (tiles.0 * tilesize, tiles.1 * tilesize);
+        let len = (tiles.0 * tiles.1 * tilesize * tilesize * 4) as usize;
+        let mapping: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+        let expected = baseline_to_image(&mapping, tilesize, tiles, size);
+        let actual = to_image(&mapping, tilesize, tiles, size);
+
+        assert_eq!(expected.dimensions(), actual.dimensions());
+        assert_eq!(expected.into_raw(), actual.into_raw());
+    }
+
+    #[test]
+    fn odd_tile_rows_rotate_subtile_order_rather_than_reversing_it() {
+        assert_eq!(
+            rotated_order(Vc4Tile.subtile_order()),
+            [(1, 1), (1, 0), (0, 0), (0, 1)]
+        );
+    }
+}
+
+/// GPU-accelerated format conversion, mirroring the CPU decoders above. Requires
+/// the `gpu` feature and a `wgpu`-compatible adapter; falling back to the CPU
+/// path when none is available is left to the caller.
+#[cfg(feature = "gpu")]
+pub mod gpu {
+    use super::{TileFormat, YuvMatrix};
+    use image::{GenericImage, RgbImage};
+    use wgpu::util::DeviceExt;
+
+    const YUV_SHADER: &str = include_str!("shaders/yuv_to_rgb.wgsl");
+    const DETILE_SHADER: &str = include_str!("shaders/detile.wgsl");
+
+    /// A `wgpu` device/queue and compute pipelines, reused across frames.
+    pub struct GpuContext {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        yuv_pipeline: wgpu::ComputePipeline,
+        detile_pipeline: wgpu::ComputePipeline,
+    }
+
+    impl GpuContext {
+        pub async fn new() -> Option<GpuContext> {
+            let instance = wgpu::Instance::default();
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::LowPower,
+                    ..Default::default()
+                })
+                .await?;
+
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor::default(), None)
+                .await
+                .ok()?;
+
+            let yuv_pipeline = Self::compute_pipeline(&device, YUV_SHADER, "yuv_to_rgb");
+            let detile_pipeline = Self::compute_pipeline(&device, DETILE_SHADER, "detile");
+
+            Some(GpuContext {
+                device,
+                queue,
+                yuv_pipeline,
+                detile_pipeline,
+            })
+        }
+
+        fn compute_pipeline(
+            device: &wgpu::Device,
+            source: &str,
+            entry_point: &str,
+        ) -> wgpu::ComputePipeline {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(entry_point),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: None,
+                module: &module,
+                entry_point,
+            })
+        }
 
-            for xtile in 0..tiles.0 {
-                copy_tile(xtile * tilesize, ytile * tilesize);
+        fn storage_buffer(&self, label: &str, contents: &[u8]) -> wgpu::Buffer {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                })
+        }
+
+        /// For `var<uniform>` bindings, which need `UNIFORM` usage rather than `STORAGE` —
+        /// the auto-derived bind group layout rejects a storage buffer there.
+        fn uniform_buffer(&self, label: &str, contents: &[u8]) -> wgpu::Buffer {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+        }
+
+        async fn dispatch_and_readback(
+            &self,
+            pipeline: &wgpu::ComputePipeline,
+            bind_group: &wgpu::BindGroup,
+            workgroups: (u32, u32, u32),
+            out_buffer: &wgpu::Buffer,
+            out_len: usize,
+        ) -> Vec<u32> {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: None,
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(workgroups.0, workgroups.1, workgroups.2);
             }
-        } else {
-            let mut copy_tile = |x, y| {
-                copy_16x16_px(x + 16, y + 16);
-                copy_16x16_px(x + 16, y);
-                copy_16x16_px(x, y);
-                copy_16x16_px(x, y + 16);
+
+            let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("readback"),
+                size: (out_len * std::mem::size_of::<u32>()) as u64,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            encoder.copy_buffer_to_buffer(out_buffer, 0, &readback, 0, readback.size());
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = readback.slice(..);
+            let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+            slice.map_async(wgpu::MapMode::Read, move |res| tx.send(res).unwrap());
+            self.device.poll(wgpu::Maintain::Wait);
+            rx.receive().await.unwrap().unwrap();
+
+            let result = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            readback.unmap();
+            result
+        }
+
+        /// GPU counterpart of [`super::decode_image_multichannel`].
+        pub async fn decode_image_multichannel(
+            &self,
+            mappings: [&[u8]; 3],
+            size: (u32, u32),
+            pitches: [u32; 3],
+            matrix: &YuvMatrix,
+        ) -> RgbImage {
+            #[repr(C)]
+            #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+            struct Uniform {
+                coefficients: [i32; 6],
+                width: u32,
+                height: u32,
+                pitch_y: u32,
+                pitch_u: u32,
+                pitch_v: u32,
+            }
+
+            let uniform = Uniform {
+                coefficients: matrix.coefficients(),
+                width: size.0,
+                height: size.1,
+                pitch_y: pitches[0],
+                pitch_u: pitches[1],
+                pitch_v: pitches[2],
             };
 
-            for xtile in (0..tiles.0).rev() {
-                copy_tile(xtile * tilesize, ytile * tilesize);
+            let uniform_buffer = self.uniform_buffer("yuv_matrix", bytemuck::bytes_of(&uniform));
+            let plane_y = self.storage_buffer("plane_y", mappings[0]);
+            let plane_u = self.storage_buffer("plane_u", mappings[1]);
+            let plane_v = self.storage_buffer("plane_v", mappings[2]);
+
+            let pixel_count = (size.0 * size.1) as usize;
+            let out_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("rgb_out"),
+                size: (pixel_count * std::mem::size_of::<u32>()) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+
+            let layout = self.yuv_pipeline.get_bind_group_layout(0);
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: plane_y.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: plane_u.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: plane_v.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: out_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let workgroups = (size.0.div_ceil(8), size.1.div_ceil(8), 1);
+            let packed = self
+                .dispatch_and_readback(
+                    &self.yuv_pipeline,
+                    &bind_group,
+                    workgroups,
+                    &out_buffer,
+                    pixel_count,
+                )
+                .await;
+
+            let mut img = RgbImage::new(size.0, size.1);
+            for (i, word) in packed.into_iter().enumerate() {
+                let x = (i as u32) % size.0;
+                let y = (i as u32) / size.0;
+                let px = image::Rgb([word as u8, (word >> 8) as u8, (word >> 16) as u8]);
+                unsafe { img.unsafe_put_pixel(x, y, px) };
+            }
+            img
+        }
+
+        /// GPU counterpart of [`super::decode`], using the same `pixel_offset` mapping.
+        pub async fn decode_tiled<F: TileFormat>(
+            &self,
+            format: &F,
+            mapping: &[u8],
+            tiles: (u32, u32),
+            size: (u32, u32),
+        ) -> RgbImage {
+            let (sw, sh) = format.subtile_size();
+            let tile_w = sw * 2;
+            let tile_h = sh * 2;
+            let full_w = tiles.0 * tile_w;
+            let full_h = tiles.1 * tile_h;
+
+            // Matches `super::decode`'s traversal order; only the per-pixel gather moves to the GPU.
+            let order = format.subtile_order();
+            let odd_row_order = super::rotated_order(order);
+            let alternates_rows = format.alternates_rows();
+            let mut offsets = vec![0u32; (full_w * full_h) as usize];
+            let mut block_index = 0;
+            for ytile in 0..tiles.1 {
+                let reversed = alternates_rows && ytile % 2 != 0;
+                let xtiles: Box<dyn Iterator<Item = u32>> = if reversed {
+                    Box::new((0..tiles.0).rev())
+                } else {
+                    Box::new(0..tiles.0)
+                };
+
+                for xtile in xtiles {
+                    let tile_x = xtile * tile_w;
+                    let tile_y = ytile * tile_h;
+
+                    let subtiles: &[(u32, u32); 4] = if reversed { &odd_row_order } else { &order };
+
+                    for &(sx, sy) in subtiles {
+                        let ox = tile_x + sx * sw;
+                        let oy = tile_y + sy * sh;
+
+                        for py in 0..sh {
+                            for px in 0..sw {
+                                let offset = format.pixel_offset(block_index, (px, py));
+                                offsets[((oy + py) * full_w + (ox + px)) as usize] = offset as u32;
+                            }
+                        }
+
+                        block_index += 1;
+                    }
+                }
+            }
+
+            let src_buffer = self.storage_buffer("tiled_src", mapping);
+            let offsets_buffer =
+                self.storage_buffer("pixel_offsets", bytemuck::cast_slice(&offsets));
+
+            let pixel_count = offsets.len();
+            let out_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("rgb_out"),
+                size: (pixel_count * std::mem::size_of::<u32>()) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+
+            let layout = self.detile_pipeline.get_bind_group_layout(0);
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: src_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: offsets_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: out_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let workgroups = ((pixel_count as u32).div_ceil(64), 1, 1);
+            let packed = self
+                .dispatch_and_readback(
+                    &self.detile_pipeline,
+                    &bind_group,
+                    workgroups,
+                    &out_buffer,
+                    pixel_count,
+                )
+                .await;
+
+            let mut img = RgbImage::new(full_w, full_h);
+            for (i, word) in packed.into_iter().enumerate() {
+                let x = (i as u32) % full_w;
+                let y = (i as u32) / full_w;
+                let px = image::Rgb([word as u8, (word >> 8) as u8, (word >> 16) as u8]);
+                unsafe { img.unsafe_put_pixel(x, y, px) };
             }
+            img.sub_image(0, 0, size.0, size.1).to_image()
         }
     }
 
-    img.sub_image(0, 0, size.0, size.1).to_image()
+    /// Skips (rather than fails) when no adapter is present, since CI runners
+    /// commonly have none.
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::image_decoder::{decode_image_multichannel, ColorSpace, Range, YuvMatrix};
+
+        #[test]
+        fn decode_image_multichannel_matches_cpu_for_a_small_frame() {
+            pollster::block_on(async {
+                let ctx = match GpuContext::new().await {
+                    Some(ctx) => ctx,
+                    None => return,
+                };
+
+                let size = (4, 4);
+                let pitches = [4, 2, 2];
+                let y = vec![128u8; 16];
+                let u = vec![128u8; 4];
+                let v = vec![128u8; 4];
+                let matrix = YuvMatrix::new(ColorSpace::Bt601, Range::Limited);
+
+                let cpu = decode_image_multichannel([&y, &u, &v], size, pitches, &matrix);
+                let gpu = ctx
+                    .decode_image_multichannel([&y, &u, &v], size, pitches, &matrix)
+                    .await;
+
+                assert_eq!(cpu.into_raw(), gpu.into_raw());
+            });
+        }
+    }
 }
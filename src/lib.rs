@@ -0,0 +1 @@
+pub mod image_decoder;